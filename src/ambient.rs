@@ -0,0 +1,130 @@
+/*!
+	Ambient "current dispatcher" storage shared by every [`dispatcher!`]-generated type's
+	`with_default`/`set_global_default`/`dispatch_default` associated functions.
+
+	Modeled on `tracing-core`'s dispatcher ergonomics: a thread-local default takes priority, falling
+	back to a process-wide global default if no thread-local one is set. Keyed by `TypeId` so this one
+	table backs every dispatcher type, rather than needing a distinct static per macro expansion.
+
+	Re-entrant use is also guarded against here: a listener that (directly, or indirectly through
+	`with_default`/`set_global_default` on some other dispatcher of the same type) triggers a second
+	[`with_current`] lookup for a `T` that's already mid-dispatch would otherwise receive a second,
+	aliasing `&mut T`. Such a lookup is treated as if no dispatcher were installed at all, rather than
+	handed out again.
+
+	Unlike the thread-local default, the global default can be reached and dispatched to from any
+	thread, so [`set_global_default`] requires `T: Send + Sync` (the `dispatcher!` macro only ever
+	calls it for the `; Sync` form) and [`with_current`] holds the global table's lock for the whole
+	call, not just the lookup, so two threads racing on the same global default serialize rather than
+	both materializing a `&mut T` to it.
+
+	[`dispatcher!`]: crate::dispatcher
+*/
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+
+thread_local! {
+	static CURRENT: RefCell<HashMap<TypeId, *mut ()>> = RefCell::new(HashMap::new());
+	static DISPATCHING: RefCell<HashSet<TypeId>> = RefCell::new(HashSet::new());
+}
+
+fn global() -> &'static Mutex<HashMap<TypeId, usize>> {
+	static GLOBAL: OnceLock<Mutex<HashMap<TypeId, usize>>> = OnceLock::new();
+	GLOBAL.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Restores the previous thread-local default for `T` (if any) when dropped, undoing whatever
+/// [`with_default`] installed. Dropped even if `f` unwinds, since it's a local of [`with_default`]
+/// that outlives the call to `f`.
+#[doc(hidden)]
+pub struct DefaultGuard<T: 'static> {
+	previous: Option<*mut ()>,
+	_marker: std::marker::PhantomData<T>,
+}
+
+impl<T: 'static> Drop for DefaultGuard<T> {
+	fn drop(&mut self) {
+		CURRENT.with(|current| {
+			let mut current = current.borrow_mut();
+			match self.previous.take() {
+				Some(ptr) => {
+					current.insert(TypeId::of::<T>(), ptr);
+				}
+				None => {
+					current.remove(&TypeId::of::<T>());
+				}
+			}
+		});
+	}
+}
+
+/// Installs `dispatcher` as the current thread's default of type `T` for the duration of `f`,
+/// restoring whatever was previously installed (if anything) once `f` returns or unwinds.
+#[doc(hidden)]
+pub fn with_default<T: 'static, R>(dispatcher: &mut T, f: impl FnOnce() -> R) -> R {
+	let ptr = dispatcher as *mut T as *mut ();
+	let previous = CURRENT.with(|current| current.borrow_mut().insert(TypeId::of::<T>(), ptr));
+	let _guard = DefaultGuard::<T> { previous, _marker: std::marker::PhantomData };
+	f()
+}
+
+/// Installs `dispatcher` as the process-wide fallback default of type `T`, used by [`with_current`]
+/// on threads that have never called [`with_default`].
+///
+/// Requires `T: Send + Sync`: unlike the thread-local default, the global default is dispatched to
+/// from whatever thread calls [`with_current`], not just the thread that installed it.
+#[doc(hidden)]
+pub fn set_global_default<T: 'static + Send + Sync>(dispatcher: &'static mut T) {
+	let ptr = dispatcher as *mut T as usize;
+	global().lock().unwrap().insert(TypeId::of::<T>(), ptr);
+}
+
+/// Marks `type_id` as mid-dispatch for the duration of the guard, so a re-entrant [`with_current`]
+/// lookup for the same `T` can tell it's already live and decline to hand out a second `&mut T`.
+struct DispatchGuard(TypeId);
+
+impl Drop for DispatchGuard {
+	fn drop(&mut self) {
+		DISPATCHING.with(|dispatching| {
+			dispatching.borrow_mut().remove(&self.0);
+		});
+	}
+}
+
+/// Resolves the ambient default of type `T` (the current thread's, or the global one if the thread
+/// hasn't set one) and passes it to `f`, or `None` if neither is set, or if this thread is already
+/// inside a `with_current` call for `T` (see the module docs). A call on another thread holding the
+/// global default makes this one wait for it rather than pass `None`.
+///
+/// # Safety
+///
+/// Callers must only use this with a `T` whose values were installed via [`with_default`] or
+/// [`set_global_default`], both of which guarantee the pointee outlives its registration.
+#[doc(hidden)]
+pub fn with_current<T: 'static, R>(f: impl FnOnce(Option<&mut T>) -> R) -> R {
+	let type_id = TypeId::of::<T>();
+	let already_dispatching = DISPATCHING.with(|dispatching| !dispatching.borrow_mut().insert(type_id));
+	if already_dispatching {
+		return f(None);
+	}
+	let _guard = DispatchGuard(type_id);
+
+	let local = CURRENT.with(|current| current.borrow().get(&type_id).copied());
+	if let Some(ptr) = local {
+		return f(Some(unsafe { &mut *(ptr as *mut T) }));
+	}
+
+	// Held for the whole call below rather than just this lookup, so that a concurrent dispatch to the
+	// same global default on another thread can't alias `&mut T` with this one: it blocks until this
+	// one finishes instead, rather than the call dropping its event on the floor. A re-entrant call on
+	// this very thread can't reach this far to deadlock on it, since the `DISPATCHING` check above
+	// already turned that case into an early `f(None)`.
+	let global = global().lock().unwrap();
+	if let Some(&ptr) = global.get(&type_id) {
+		return f(Some(unsafe { &mut *(ptr as *mut T) }));
+	}
+	drop(global);
+	f(None)
+}
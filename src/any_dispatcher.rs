@@ -0,0 +1,177 @@
+/*!
+	A dispatcher that routes many event types through a single object, keyed by each event's
+	[`TypeId`](std::any::TypeId).
+*/
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use crate::{DispatcherCommand, Listener};
+
+type Trampoline<M> = fn(&mut dyn Any, &dyn Any, &mut M);
+
+/**
+	Dispatches events of any number of different types through a single object.
+
+	A dispatcher created with [`dispatcher!`] is specialized to exactly one `(E, M)` pair at
+	compile time. `AnyDispatcher` instead keeps one listener list per concrete event type it has
+	ever seen, and routes an incoming event to the right list at runtime by its [`TypeId`].
+
+	Register listeners for an event type with [`listen`], then fire events with [`send`]. `send`
+	silently does nothing if no listener has ever been registered for that event type.
+
+	[`dispatcher!`]: crate::dispatcher
+	[`TypeId`]: std::any::TypeId
+	[`listen`]: AnyDispatcher::listen
+	[`send`]: AnyDispatcher::send
+
+	# Examples
+
+	```rust
+	use event_dispatcher::AnyDispatcher;
+
+	struct FirstEvent(i32);
+	struct SecondEvent(&'static str);
+
+	let mut dispatcher = AnyDispatcher::<()>::default();
+	dispatcher.listen(Box::new(|event: &FirstEvent, _: &mut ()| {
+		println!("first: {}", event.0);
+		None
+	}));
+	dispatcher.listen(Box::new(|event: &SecondEvent, _: &mut ()| {
+		println!("second: {}", event.0);
+		None
+	}));
+
+	dispatcher.send(&FirstEvent(42), &mut ());
+	dispatcher.send(&SecondEvent("hello"), &mut ());
+	```
+*/
+pub struct AnyDispatcher<M = ()> {
+	routes: HashMap<TypeId, (Trampoline<M>, Box<dyn Any>)>,
+}
+
+impl<M: 'static> AnyDispatcher<M> {
+	/// Registers a listener for events of type `E`. The listener will be called when [`send`] is
+	/// called with an event of that type.
+	///
+	/// [`send`]: AnyDispatcher::send
+	pub fn listen<E: 'static>(&mut self, listener: Box<dyn Listener<E, M>>) {
+		let entry = self.routes.entry(TypeId::of::<E>()).or_insert_with(|| {
+			(Self::trampoline::<E>, Box::new(Vec::<Box<dyn Listener<E, M>>>::new()) as Box<dyn Any>)
+		});
+		let listeners = entry.1.downcast_mut::<Vec<Box<dyn Listener<E, M>>>>()
+			.expect("AnyDispatcher: TypeId did not match its own route's listener vector");
+		listeners.push(listener);
+	}
+
+	/// Dispatches `event` to every [`Listener`] registered for its concrete type via [`listen`].
+	/// Does nothing if no listener has ever been registered for that type.
+	///
+	/// [`listen`]: AnyDispatcher::listen
+	pub fn send<E: 'static>(&mut self, event: &E, event_mut: &mut M) {
+		if let Some((trampoline, listeners)) = self.routes.get_mut(&TypeId::of::<E>()) {
+			trampoline(listeners.as_mut(), event, event_mut);
+		}
+	}
+
+	/// Monomorphized per `E`, downcasts both the listener vector and the incoming event back to
+	/// their concrete types, then calls each listener in turn, honoring [`DispatcherCommand`] per
+	/// listener as [`dispatcher!`] does. Unlike [`dispatcher!`], removal uses `swap_remove`, so the
+	/// last listener moves into the removed one's place in the calling order.
+	///
+	/// [`dispatcher!`]: crate::dispatcher
+	fn trampoline<E: 'static>(listeners: &mut dyn Any, event: &dyn Any, event_mut: &mut M) {
+		let listeners = listeners.downcast_mut::<Vec<Box<dyn Listener<E, M>>>>()
+			.expect("AnyDispatcher: TypeId did not match its own route's listener vector");
+		let event = event.downcast_ref::<E>()
+			.expect("AnyDispatcher: TypeId did not match the event passed to send");
+
+		let mut i = 0;
+		while i < listeners.len() {
+			let res = listeners[i].on_event(event, event_mut);
+			match res {
+				None => i += 1,
+				Some(DispatcherCommand::StopListening) => {
+					listeners.swap_remove(i);
+				}
+				Some(DispatcherCommand::StopPropagation) => {
+					break;
+				}
+				Some(DispatcherCommand::StopListeningAndPropagation) => {
+					listeners.swap_remove(i);
+					break;
+				}
+			}
+		}
+	}
+}
+
+impl<M> Default for AnyDispatcher<M> {
+	fn default() -> Self {
+		Self { routes: HashMap::new() }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::RefCell;
+	use std::rc::Rc;
+
+	use super::AnyDispatcher;
+	use crate::{DispatcherCommand, Listener};
+
+	struct FirstEvent(u32);
+	struct SecondEvent(u32);
+
+	/// Tests that listeners for different event types are kept separate and only called for their
+	/// own event type.
+	#[test]
+	fn routes_by_type() {
+		let mut dispatcher = AnyDispatcher::<u32>::default();
+		dispatcher.listen(Box::new(|event: &FirstEvent, event_mut: &mut u32| {
+			*event_mut += event.0;
+			None
+		}));
+		dispatcher.listen(Box::new(|event: &SecondEvent, event_mut: &mut u32| {
+			*event_mut += event.0 * 10;
+			None
+		}));
+
+		let mut total = 0;
+		dispatcher.send(&FirstEvent(1), &mut total);
+		dispatcher.send(&SecondEvent(2), &mut total);
+		assert_eq!(total, 21);
+	}
+
+	/// Tests that sending an event with no registered listeners is a no-op rather than a panic.
+	#[test]
+	fn send_without_listeners_is_noop() {
+		let mut dispatcher = AnyDispatcher::<u32>::default();
+		let mut total = 0;
+		dispatcher.send(&FirstEvent(1), &mut total);
+		assert_eq!(total, 0);
+	}
+
+	/// Tests that `DispatcherCommand::StopListening` still prunes the listener for its own event type.
+	#[test]
+	fn stop_listening_per_type() {
+		struct EventListener {
+			uses: u32,
+		}
+
+		impl Listener<FirstEvent, ()> for EventListener {
+			fn on_event(&mut self, _: &FirstEvent, _: &mut ()) -> Option<DispatcherCommand> {
+				self.uses += 1;
+				Some(DispatcherCommand::StopListening)
+			}
+		}
+
+		let mut dispatcher = AnyDispatcher::<()>::default();
+		let listener = Rc::new(RefCell::new(EventListener { uses: 0 }));
+		dispatcher.listen(Box::new(Rc::downgrade(&listener)));
+
+		dispatcher.send(&FirstEvent(0), &mut ());
+		dispatcher.send(&FirstEvent(0), &mut ());
+		assert_eq!(listener.borrow().uses, 1);
+	}
+}
@@ -9,6 +9,10 @@
 
 	Create a dispatcher type using the [`dispatcher!`] macro.
 
+	If you'd rather route many unrelated event types through one object than declare a dispatcher
+	type per event, use [`AnyDispatcher`] instead; it keys listeners by the event's `TypeId` at the
+	cost of a small amount of runtime dispatch.
+
 	### Usage
 
 	Create a new [dispatcher].
@@ -17,6 +21,12 @@
 
 	[Dispatch] events to the listeners.
 
+	[Unregister] a listener by the [`ListenerHandle`] returned from registering it, if you need to stop listening from outside the listener itself.
+
+	If a listener needs to raise further events of its own while handling one, implement [`QueueingListener`] instead of [`Listener`] and dispatch with `dispatch_queued`; see [`QueueingListener`] for details.
+
+	Code nested deep below where a dispatcher lives doesn't have to have it threaded all the way down: a lifetime-free dispatcher can be installed as the ambient default for the current thread (or process-wide) via `with_default`/`set_global_default`, and reached from anywhere with `dispatch_default`.
+
 	### Examples
 
 	#### Using an owned listener:
@@ -38,8 +48,9 @@
 			}
 		);
 
-		dispatcher.add(closure);
+		let handle = dispatcher.add(closure);
 		dispatcher.dispatch(&Event(42), &mut ());
+		dispatcher.remove(handle);
 	}
 	```
 
@@ -76,14 +87,68 @@
 	[dispatcher]: struct.DispatcherType.html
 	[Register]: struct.DispatcherType.html#method.add
 	[Dispatch]: struct.DispatcherType.html#method.dispatch
+	[Unregister]: struct.DispatcherType.html#method.remove
 	[`DispatcherType`]: struct.DispatcherType.html
+	[`ListenerHandle`]: struct.ListenerHandle.html
 */
 use std::cell::RefCell;
 use std::rc::Weak;
+use std::sync::{Mutex, RwLock};
+
+mod ambient;
+mod any_dispatcher;
+mod slab;
 
 #[cfg(test)]
 mod tests;
 
+pub use any_dispatcher::AnyDispatcher;
+#[doc(hidden)]
+pub use ambient::{set_global_default, with_current, with_default};
+#[doc(hidden)]
+pub use slab::Slab;
+
+/**
+	A handle to a registered [`Listener`], returned by [`add`] and accepted by [`remove`].
+
+	Handles stay valid for as long as the listener they refer to is registered, even as other listeners
+	are added to or removed from the same dispatcher. Each dispatcher numbers its handles independently,
+	so two dispatchers can issue equal handles, and [`remove`] does not check which dispatcher issued one.
+
+	A handle also carries a generation counter internally, so that if the listener it refers to is
+	removed and its slot later reused by an unrelated listener, [`remove`] rejects the stale handle
+	instead of mistakenly unregistering the new listener occupying that slot.
+
+	[`Listener`]: trait.Listener.html
+	[`add`]: struct.DispatcherType.html#method.add
+	[`remove`]: struct.DispatcherType.html#method.remove
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListenerHandle {
+	key: usize,
+	generation: u64,
+}
+
+impl ListenerHandle {
+	// Not `pub(crate)`: `dispatcher!` expands into the invoking crate, which macro hygiene does not
+	// exempt from ordinary visibility rules for a struct's private fields, so this plumbing has to be
+	// reachable from outside this crate too.
+	#[doc(hidden)]
+	pub fn new((key, generation): (usize, u64)) -> Self {
+		ListenerHandle { key, generation }
+	}
+
+	#[doc(hidden)]
+	pub fn key(self) -> usize {
+		self.key
+	}
+
+	#[doc(hidden)]
+	pub fn generation(self) -> u64 {
+		self.generation
+	}
+}
+
 /**
 	Special commands [`Listener`]s can return to the dispatcher to influence dispatching.
 
@@ -137,6 +202,104 @@ impl<E, M, L: Listener<E, M>> Listener<E, M> for Weak<RefCell<L>> {
 	}
 }
 
+/// Allows weak references behind a `Mutex` to be used as event listeners themselves, for use with a
+/// dispatcher created via `dispatcher!(...; Sync)`.
+impl<E, M, L: Listener<E, M>> Listener<E, M> for std::sync::Weak<Mutex<L>> {
+	fn on_event(&mut self, event: &E, event_mut: &mut M) -> Option<DispatcherCommand> {
+		if let Some(listener_arc) = self.upgrade() {
+			let mut listener = listener_arc.lock().unwrap();
+			listener.on_event(event, event_mut)
+		} else {
+			Some(DispatcherCommand::StopListening)
+		}
+	}
+}
+
+/// Allows weak references behind an `RwLock` to be used as event listeners themselves, for use with a
+/// dispatcher created via `dispatcher!(...; Sync)`.
+impl<E, M, L: Listener<E, M>> Listener<E, M> for std::sync::Weak<RwLock<L>> {
+	fn on_event(&mut self, event: &E, event_mut: &mut M) -> Option<DispatcherCommand> {
+		if let Some(listener_arc) = self.upgrade() {
+			let mut listener = listener_arc.write().unwrap();
+			listener.on_event(event, event_mut)
+		} else {
+			Some(DispatcherCommand::StopListening)
+		}
+	}
+}
+
+/**
+	Like [`Listener`], but also receives an [`EventSink`] it can push follow-up events into.
+
+	Ordinary [`Listener`]s can't raise new events themselves, because [`dispatch`] already holds the
+	dispatcher mutably while calling them. A `QueueingListener` instead queues events to be dispatched
+	*after* the current one finishes, via [`add_queueing`] and [`dispatch_queued`].
+
+	[`Listener`]: trait.Listener.html
+	[`dispatch`]: struct.DispatcherType.html#method.dispatch
+	[`EventSink`]: struct.EventSink.html
+	[`add_queueing`]: struct.DispatcherType.html#method.add_queueing
+	[`dispatch_queued`]: struct.DispatcherType.html#method.dispatch_queued
+*/
+pub trait QueueingListener<E=(), M=()> {
+	/**
+		This function will be called once a dispatcher you are registered with has an event to dispatch.
+		You can influence the dispatcher with the return value, see [`DispatcherCommand`] for details, or
+		queue follow-up events to be dispatched once the current one finishes via `sink`.
+
+		[`DispatcherCommand`]: enum.DispatcherCommand.html
+	*/
+	fn on_event(&mut self, event: &E, event_mut: &mut M, sink: &mut EventSink<E>) -> Option<DispatcherCommand>;
+}
+
+/// Allows closures to be used as queueing event listeners.
+impl<E, M, F> QueueingListener<E, M> for F where F: FnMut(&E, &mut M, &mut EventSink<E>) -> Option<DispatcherCommand> {
+	fn on_event(&mut self, event: &E, event_mut: &mut M, sink: &mut EventSink<E>) -> Option<DispatcherCommand> {
+		(self)(event, event_mut, sink)
+	}
+}
+
+/// Allows weak references to event listeners to be used as queueing event listeners themselves.
+impl<E, M, L: QueueingListener<E, M>> QueueingListener<E, M> for Weak<RefCell<L>> {
+	fn on_event(&mut self, event: &E, event_mut: &mut M, sink: &mut EventSink<E>) -> Option<DispatcherCommand> {
+		if let Some(listener_rc) = self.upgrade() {
+			let mut listener = listener_rc.borrow_mut();
+			listener.on_event(event, event_mut, sink)
+		} else {
+			Some(DispatcherCommand::StopListening)
+		}
+	}
+}
+
+/**
+	Passed to a [`QueueingListener`] during [`dispatch_queued`], letting it queue events of the same
+	type `E` to be dispatched once the current event's listeners have all run.
+
+	Queuing is breadth-first: every listener sees the event currently being dispatched before any
+	event queued during that dispatch is itself dispatched.
+
+	[`QueueingListener`]: trait.QueueingListener.html
+	[`dispatch_queued`]: struct.DispatcherType.html#method.dispatch_queued
+*/
+pub struct EventSink<'a, E> {
+	queue: &'a mut std::collections::VecDeque<E>,
+}
+
+impl<'a, E> EventSink<'a, E> {
+	// Not `pub(crate)`: `dispatcher!` expands into the invoking crate, which macro hygiene does not
+	// exempt from ordinary visibility rules for a private field, so this plumbing has to be
+	// reachable from outside this crate too.
+	#[doc(hidden)]
+	pub fn new(queue: &'a mut std::collections::VecDeque<E>) -> Self {
+		EventSink { queue }
+	}
+
+	/// Queues `event` to be dispatched after the current wave of listeners has finished running.
+	pub fn push(&mut self, event: E) {
+		self.queue.push_back(event);
+	}
+}
+
 /**
 	Macro to create a dispatcher type specialized to event types.
 
@@ -144,9 +307,31 @@ impl<E, M, L: Listener<E, M>> Listener<E, M> for Weak<RefCell<L>> {
 
 	Call using the name you want to call your dispatcher, the type of event references, the type of mutable event references, and any lifetimes that the event types include.
 
+	Append `; Sync` after the lifetimes to instead generate a dispatcher whose listeners must be
+	`Send + Sync`, so the dispatcher itself can be shared across threads behind an `Arc`. The
+	[`Weak<Mutex<L>>`] and [`Weak<RwLock<L>>`] listener impls mirror the `Weak<RefCell<L>>` one and are
+	accepted by every form; unlike `Weak<RefCell<L>>`, they also meet that bound (for `L: Send` and
+	`L: Send + Sync` respectively).
+
+	The lifetime-free form (no lifetimes after `$event_mut`) additionally gets [`add_queueing`]/
+	[`dispatch_queued`] (re-entrant, queued dispatch) and `with_default`/`dispatch_default` (an
+	ambient-dispatcher layer modeled on `tracing-core`; see [`DispatcherType::with_default`] for
+	details). The form that takes lifetimes gets neither. Queued dispatch stores events of type
+	`$event` past the call that received them, which isn't sound if `$event` can itself borrow data
+	that doesn't live that long.
+
+	`set_global_default` is narrower still: only the lifetime-free `; Sync` form gets it, since
+	installing a global default hands `&mut Self` to whatever thread calls `dispatch_default`, which
+	requires `Self: Send + Sync`.
+
 	See [`DispatcherType`] for documentation on the created type.
 
 	[`DispatcherType`]: struct.DispatcherType.html
+	[`DispatcherType::with_default`]: struct.DispatcherType.html#method.with_default
+	[`add_queueing`]: struct.DispatcherType.html#method.add_queueing
+	[`dispatch_queued`]: struct.DispatcherType.html#method.dispatch_queued
+	[`Weak<Mutex<L>>`]: https://doc.rust-lang.org/std/sync/struct.Weak.html
+	[`Weak<RwLock<L>>`]: https://doc.rust-lang.org/std/sync/struct.Weak.html
 
 	# Examples
 
@@ -160,15 +345,36 @@ impl<E, M, L: Listener<E, M>> Listener<E, M> for Weak<RefCell<L>> {
 
 	dispatcher!(MyDispatcher<u32, ()>);
 	dispatcher!(MyAdvancedDispatcher<Event<'a, 'b>, Event<'c, 'd>>, 'a, 'b, 'c, 'd);
+	dispatcher!(MySyncDispatcher<u32, ()>; Sync);
 
 	// angle brackets not needed afterwards
 	let dispatcher_instance = MyDispatcher::default();
 	let adv_disp_instance = MyAdvancedDispatcher::default();
+	let sync_disp_instance = MySyncDispatcher::default();
 	```
 */
 #[macro_export]
 macro_rules! dispatcher {
-	($disp_name:ident<$event:ty, $event_mut:ty>$(, $lifetime:tt)*) => {
+	($disp_name:ident<$event:ty, $event_mut:ty>) => {
+		$crate::dispatcher!(@plain $disp_name<$event, $event_mut>; );
+		$crate::dispatcher!(@ambient $disp_name<$event, $event_mut>);
+	};
+	($disp_name:ident<$event:ty, $event_mut:ty>; Sync) => {
+		$crate::dispatcher!(@plain $disp_name<$event, $event_mut>; + Send + Sync);
+		$crate::dispatcher!(@ambient $disp_name<$event, $event_mut>);
+		$crate::dispatcher!(@ambient_sync $disp_name<$event, $event_mut>);
+	};
+	($disp_name:ident<$event:ty, $event_mut:ty>, $($lifetime:tt),+) => {
+		$crate::dispatcher!(@lifetime $disp_name<$event, $event_mut>, $($lifetime),+; );
+	};
+	($disp_name:ident<$event:ty, $event_mut:ty>, $($lifetime:tt),+; Sync) => {
+		$crate::dispatcher!(@lifetime $disp_name<$event, $event_mut>, $($lifetime),+; + Send + Sync);
+	};
+
+	// The lifetime-free form: `$event`/`$event_mut` have no unbound lifetimes, so (unlike the
+	// `@lifetime` form below) it's sound to store them past the call that handed them in, which is
+	// what queued re-entrant dispatch does. The ambient-default layer is only generated alongside it.
+	(@plain $disp_name:ident<$event:ty, $event_mut:ty>; $($bound:tt)*) => {
 		/**
 			Docs-only metavariable: Use the [`dispatcher!`] macro to create this type in your code.
 
@@ -176,61 +382,343 @@ macro_rules! dispatcher {
 
 			Allows listeners to be registered using [`add`], and events to be dispatched to those listeners using [`dispatch`].
 
+			Also supports a re-entrant, queued mode: listeners registered with [`add_queueing`] can raise
+			follow-up events from inside their own `on_event`, dispatched via [`dispatch_queued`].
+
 			[`dispatcher!`]: macro.dispatcher.html
 			[`add`]: struct.DispatcherType.html#method.add
 			[`dispatch`]: struct.DispatcherType.html#method.dispatch
+			[`add_queueing`]: struct.DispatcherType.html#method.add_queueing
+			[`dispatch_queued`]: struct.DispatcherType.html#method.dispatch_queued
 		*/
+		// Not every invocation of this macro uses every generated method (e.g. a dispatcher that
+		// never registers a `QueueingListener` has no use for `dispatch_queued`), so dead_code can't
+		// tell a deliberately-unused part of the generated API from an actual mistake.
+		#[allow(dead_code)]
 		struct $disp_name {
-			listeners: Vec<Box<dyn for<$($lifetime,)*> Listener<$event, $event_mut>>>,
+			listeners: $crate::Slab<Box<dyn Listener<$event, $event_mut> $($bound)*>>,
+			queueing_listeners: $crate::Slab<Box<dyn $crate::QueueingListener<$event, $event_mut> $($bound)*>>,
+			pending: std::collections::VecDeque<$event>,
+			max_queue_depth: usize,
 		}
 
+		#[allow(dead_code)]
 		impl $disp_name {
 			/**
 				Adds a listener to listen for an event. The listener will be called when [`dispatch`] is called.
 
+				Returns a [`ListenerHandle`] that can be passed to [`remove`] to unregister the listener again
+				from outside the listener itself.
+
 				[`dispatch`]: struct.DispatcherType.html#method.dispatch
+				[`ListenerHandle`]: struct.ListenerHandle.html
+				[`remove`]: struct.DispatcherType.html#method.remove
+			*/
+			pub fn add(&mut self, listener: Box<dyn Listener<$event, $event_mut> $($bound)*>) -> $crate::ListenerHandle {
+				$crate::ListenerHandle::new(self.listeners.insert(listener))
+			}
+
+			/**
+				Removes a previously [`add`]ed listener by the handle it was registered with.
+
+				Returns `true` if a listener was removed, `false` if the handle no longer refers to a
+				registered listener (it may already have removed itself, e.g. via [`DispatcherCommand::StopListening`]).
+
+				[`add`]: struct.DispatcherType.html#method.add
+				[`DispatcherCommand::StopListening`]: enum.DispatcherCommand.html#variant.StopListening
 			*/
-			pub fn add(&mut self, listener: Box<dyn for<$($lifetime,)*> Listener<$event, $event_mut>>) {
-				self.listeners.push(listener);
+			pub fn remove(&mut self, handle: $crate::ListenerHandle) -> bool {
+				self.listeners.remove_handle(handle.key(), handle.generation()).is_some()
 			}
 
 			/**
 				Calls all registered [`Listener`]s via their implemented [`on_event`] method.
 				Listeners can influence the dispatcher with the return value, see [`DispatcherCommand`] for details.
 
+				Listeners are walked by their stable keys: one that removes itself via [`DispatcherCommand`]
+				leaves its slot vacant without moving any other listener's key. Listeners get no access to
+				the dispatcher during this call, so one cannot `remove` another listener's handle mid-walk.
+
 				[`Listener`]: trait.Listener.html
 				[`on_event`]: trait.Listener.html#tymethod.on_event
 				[`DispatcherCommand`]: enum.DispatcherCommand.html
 			*/
-			pub fn dispatch<$($lifetime,)*>(&mut self, event:&$event, event_mut: &mut $event_mut) {
-				let mut i = 0;
-				while i < self.listeners.len() {
-					let res = self.listeners[i].on_event(event, event_mut);
+			pub fn dispatch(&mut self, event: &$event, event_mut: &mut $event_mut) {
+				let mut key = 0;
+				while key < self.listeners.key_bound() {
+					let listener = match self.listeners.get_mut(key) {
+						Some(listener) => listener,
+						None => {
+							key += 1;
+							continue;
+						}
+					};
+					let res = listener.on_event(event, event_mut);
 					match res {
-						None => i += 1,
+						None => key += 1,
 						Some(DispatcherCommand::StopListening) => {
-							self.listeners.swap_remove(i);
+							self.listeners.remove(key);
+							key += 1;
 						}
 						Some(DispatcherCommand::StopPropagation) => {
 							break;
 						}
 						Some(DispatcherCommand::StopListeningAndPropagation) => {
-							self.listeners.swap_remove(i);
+							self.listeners.remove(key);
 							break;
 						}
 					}
 				}
 			}
+
+			/**
+				Adds a [`QueueingListener`], which can queue follow-up events to be dispatched by
+				[`dispatch_queued`] once the event it is currently handling finishes.
+
+				[`QueueingListener`]: trait.QueueingListener.html
+				[`dispatch_queued`]: struct.DispatcherType.html#method.dispatch_queued
+			*/
+			pub fn add_queueing(&mut self, listener: Box<dyn $crate::QueueingListener<$event, $event_mut> $($bound)*>) -> $crate::ListenerHandle {
+				$crate::ListenerHandle::new(self.queueing_listeners.insert(listener))
+			}
+
+			/// Removes a previously [`add_queueing`]ed listener by the handle it was registered with.
+			///
+			/// [`add_queueing`]: struct.DispatcherType.html#method.add_queueing
+			pub fn remove_queueing(&mut self, handle: $crate::ListenerHandle) -> bool {
+				self.queueing_listeners.remove_handle(handle.key(), handle.generation()).is_some()
+			}
+
+			/**
+				Caps how many events (the one passed in included) [`dispatch_queued`] will dispatch for a
+				single call; events still queued once the cap is reached are dropped, so that listeners
+				which always queue another event can't loop forever. Defaults to 1024.
+
+				[`dispatch_queued`]: struct.DispatcherType.html#method.dispatch_queued
+			*/
+			pub fn set_max_queue_depth(&mut self, max_queue_depth: usize) {
+				self.max_queue_depth = max_queue_depth;
+			}
+
+			/**
+				Dispatches `event` to the registered [`QueueingListener`]s, then repeats for any events
+				they queued via their [`EventSink`], and so on, until the queue is empty or
+				[`set_max_queue_depth`] events have been dispatched, dropping whatever is still queued then.
+
+				Dispatching is breadth-first: every [`QueueingListener`] is called for the current event
+				before any event queued during that call is itself dispatched.
+
+				[`QueueingListener`]: trait.QueueingListener.html
+				[`EventSink`]: struct.EventSink.html
+				[`set_max_queue_depth`]: struct.DispatcherType.html#method.set_max_queue_depth
+			*/
+			pub fn dispatch_queued(&mut self, event: $event, event_mut: &mut $event_mut) {
+				self.pending.push_back(event);
+				let mut depth = 0;
+				while let Some(event) = self.pending.pop_front() {
+					if depth >= self.max_queue_depth {
+						self.pending.clear();
+						break;
+					}
+					depth += 1;
+
+					let mut key = 0;
+					while key < self.queueing_listeners.key_bound() {
+						let listener = match self.queueing_listeners.get_mut(key) {
+							Some(listener) => listener,
+							None => {
+								key += 1;
+								continue;
+							}
+						};
+						let mut sink = $crate::EventSink::new(&mut self.pending);
+						let res = listener.on_event(&event, event_mut, &mut sink);
+						match res {
+							None => key += 1,
+							Some(DispatcherCommand::StopListening) => {
+								self.queueing_listeners.remove(key);
+								key += 1;
+							}
+							Some(DispatcherCommand::StopPropagation) => {
+								break;
+							}
+							Some(DispatcherCommand::StopListeningAndPropagation) => {
+								self.queueing_listeners.remove(key);
+								break;
+							}
+						}
+					}
+				}
+			}
+
 		}
 
 		impl Default for $disp_name {
 			fn default() -> Self {
 				Self {
-					listeners: vec![],
+					listeners: $crate::Slab::new(),
+					queueing_listeners: $crate::Slab::new(),
+					pending: std::collections::VecDeque::new(),
+					max_queue_depth: 1024,
 				}
 			}
 		}
-	}
+	};
+
+	// Generated for every lifetime-free dispatcher, `; Sync` or not: installing and dispatching to a
+	// thread-local default never leaves the thread that called `with_default`, so it's sound
+	// regardless of whether `$disp_name` is `Send`/`Sync`.
+	(@ambient $disp_name:ident<$event:ty, $event_mut:ty>) => {
+		#[allow(dead_code)]
+		impl $disp_name {
+			/**
+				Installs `self` as the current thread's default dispatcher for the duration of `f`,
+				restoring whatever was previously installed (if anything) once `f` returns or unwinds.
+
+				[`dispatch_default`]: struct.DispatcherType.html#method.dispatch_default
+			*/
+			pub fn with_default<R>(&mut self, f: impl FnOnce() -> R) -> R {
+				$crate::with_default(self, f)
+			}
+
+			/**
+				Dispatches `event` to the ambient dispatcher: the current thread's [`with_default`]
+				dispatcher if one is installed, otherwise the process-wide [`set_global_default`]
+				dispatcher if one has been installed for this type (only possible for the `; Sync`
+				form of this macro). Does nothing if neither is installed, or if this thread is already
+				inside `dispatch_default` for this type (i.e. this call is re-entrant). A call on another
+				thread that is dispatching to the same global default makes this one wait for it instead.
+
+				[`with_default`]: struct.DispatcherType.html#method.with_default
+				[`set_global_default`]: struct.DispatcherType.html#method.set_global_default
+			*/
+			pub fn dispatch_default(event: &$event, event_mut: &mut $event_mut) {
+				$crate::with_current::<Self, _>(|dispatcher| {
+					if let Some(dispatcher) = dispatcher {
+						dispatcher.dispatch(event, event_mut);
+					}
+				})
+			}
+		}
+	};
+
+	// Only generated for the `; Sync` form: the global default is reachable from any thread, so
+	// installing one requires `Self: Send + Sync`, which only the `; Sync` form's listener storage
+	// guarantees.
+	(@ambient_sync $disp_name:ident<$event:ty, $event_mut:ty>) => {
+		#[allow(dead_code)]
+		impl $disp_name {
+			/**
+				Installs `dispatcher` as the process-wide fallback default, used by [`dispatch_default`]
+				on threads that haven't called [`with_default`].
+
+				[`dispatch_default`]: struct.DispatcherType.html#method.dispatch_default
+				[`with_default`]: struct.DispatcherType.html#method.with_default
+			*/
+			pub fn set_global_default(dispatcher: &'static mut Self) {
+				$crate::set_global_default(dispatcher)
+			}
+		}
+	};
+
+	// The lifetime-carrying form: `$event`/`$event_mut` may borrow data with lifetimes not named
+	// anywhere on `$disp_name` itself, so no event here may outlive a single `dispatch` call - no
+	// queued dispatch. The ambient-default layer isn't generated for this form either.
+	(@lifetime $disp_name:ident<$event:ty, $event_mut:ty>$(, $lifetime:tt)*; $($bound:tt)*) => {
+		/**
+			Docs-only metavariable: Use the [`dispatcher!`] macro to create this type in your code.
+
+			Routes events to registered listeners.
+
+			Allows listeners to be registered using [`add`], and events to be dispatched to those listeners using [`dispatch`].
+
+			[`dispatcher!`]: macro.dispatcher.html
+			[`add`]: struct.DispatcherType.html#method.add
+			[`dispatch`]: struct.DispatcherType.html#method.dispatch
+		*/
+		#[allow(dead_code)]
+		struct $disp_name {
+			listeners: $crate::Slab<Box<dyn for<$($lifetime,)*> Listener<$event, $event_mut> $($bound)*>>,
+		}
+
+		#[allow(dead_code)]
+		impl $disp_name {
+			/**
+				Adds a listener to listen for an event. The listener will be called when [`dispatch`] is called.
+
+				Returns a [`ListenerHandle`] that can be passed to [`remove`] to unregister the listener again
+				from outside the listener itself.
+
+				[`dispatch`]: struct.DispatcherType.html#method.dispatch
+				[`ListenerHandle`]: struct.ListenerHandle.html
+				[`remove`]: struct.DispatcherType.html#method.remove
+			*/
+			pub fn add(&mut self, listener: Box<dyn for<$($lifetime,)*> Listener<$event, $event_mut> $($bound)*>) -> $crate::ListenerHandle {
+				$crate::ListenerHandle::new(self.listeners.insert(listener))
+			}
+
+			/**
+				Removes a previously [`add`]ed listener by the handle it was registered with.
+
+				Returns `true` if a listener was removed, `false` if the handle no longer refers to a
+				registered listener (it may already have removed itself, e.g. via [`DispatcherCommand::StopListening`]).
+
+				[`add`]: struct.DispatcherType.html#method.add
+				[`DispatcherCommand::StopListening`]: enum.DispatcherCommand.html#variant.StopListening
+			*/
+			pub fn remove(&mut self, handle: $crate::ListenerHandle) -> bool {
+				self.listeners.remove_handle(handle.key(), handle.generation()).is_some()
+			}
+
+			/**
+				Calls all registered [`Listener`]s via their implemented [`on_event`] method.
+				Listeners can influence the dispatcher with the return value, see [`DispatcherCommand`] for details.
+
+				Listeners are walked by their stable keys: one that removes itself via [`DispatcherCommand`]
+				leaves its slot vacant without moving any other listener's key. Listeners get no access to
+				the dispatcher during this call, so one cannot `remove` another listener's handle mid-walk.
+
+				[`Listener`]: trait.Listener.html
+				[`on_event`]: trait.Listener.html#tymethod.on_event
+				[`DispatcherCommand`]: enum.DispatcherCommand.html
+			*/
+			pub fn dispatch<$($lifetime,)*>(&mut self, event: &$event, event_mut: &mut $event_mut) {
+				let mut key = 0;
+				while key < self.listeners.key_bound() {
+					let listener = match self.listeners.get_mut(key) {
+						Some(listener) => listener,
+						None => {
+							key += 1;
+							continue;
+						}
+					};
+					let res = listener.on_event(event, event_mut);
+					match res {
+						None => key += 1,
+						Some(DispatcherCommand::StopListening) => {
+							self.listeners.remove(key);
+							key += 1;
+						}
+						Some(DispatcherCommand::StopPropagation) => {
+							break;
+						}
+						Some(DispatcherCommand::StopListeningAndPropagation) => {
+							self.listeners.remove(key);
+							break;
+						}
+					}
+				}
+			}
+		}
+
+		impl Default for $disp_name {
+			fn default() -> Self {
+				Self {
+					listeners: $crate::Slab::new(),
+				}
+			}
+		}
+	};
 }
 /*
 // todo: use this when it is stable
@@ -0,0 +1,106 @@
+/*!
+	A minimal slab allocator used internally to back dispatcher listener storage.
+
+	Unlike `Vec::swap_remove`, removing an entry never moves another entry's key, so keys
+	([`ListenerHandle`]s) handed out by [`dispatcher!`] stay valid across removals, including
+	removals that happen while a dispatch is in progress.
+
+	Each slot also carries a generation counter, bumped every time the slot is freed and reused, so a
+	[`ListenerHandle`] that outlives the listener it was issued for (because its slot was freed and
+	then reused by an unrelated listener) is rejected by [`remove_handle`] rather than silently
+	unregistering whatever now occupies that slot.
+
+	[`ListenerHandle`]: crate::ListenerHandle
+	[`dispatcher!`]: crate::dispatcher
+	[`remove_handle`]: Slab::remove_handle
+*/
+
+enum Slot<T> {
+	Occupied(T, u64),
+	Vacant(usize, u64),
+}
+
+#[doc(hidden)]
+pub struct Slab<T> {
+	slots: Vec<Slot<T>>,
+	next_free: usize,
+}
+
+impl<T> Default for Slab<T> {
+	fn default() -> Self {
+		Slab { slots: Vec::new(), next_free: 0 }
+	}
+}
+
+impl<T> Slab<T> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Inserts `value`, returning the key and generation it can later be looked up or removed by.
+	pub fn insert(&mut self, value: T) -> (usize, u64) {
+		let key = self.next_free;
+		if key == self.slots.len() {
+			self.slots.push(Slot::Occupied(value, 0));
+			self.next_free = self.slots.len();
+			(key, 0)
+		} else {
+			let generation = match &self.slots[key] {
+				Slot::Vacant(_, generation) => *generation,
+				Slot::Occupied(..) => unreachable!("slab free list points at an occupied slot"),
+			};
+			self.next_free = match std::mem::replace(&mut self.slots[key], Slot::Occupied(value, generation)) {
+				Slot::Vacant(next_free, _) => next_free,
+				Slot::Occupied(..) => unreachable!(),
+			};
+			(key, generation)
+		}
+	}
+
+	/// Removes and returns the value at `key`, if any is currently stored there, regardless of
+	/// generation. Used internally for positional removal during a dispatch, where `key` was just
+	/// read back from the slab itself rather than from a handle that may have gone stale.
+	pub fn remove(&mut self, key: usize) -> Option<T> {
+		let slot = self.slots.get_mut(key)?;
+		let generation = match slot {
+			Slot::Occupied(_, generation) => *generation,
+			Slot::Vacant(..) => return None,
+		};
+		let value = match std::mem::replace(slot, Slot::Vacant(self.next_free, generation + 1)) {
+			Slot::Occupied(value, _) => value,
+			Slot::Vacant(..) => unreachable!(),
+		};
+		self.next_free = key;
+		Some(value)
+	}
+
+	/// Removes and returns the value at `key`, if any is currently stored there *and* its generation
+	/// matches `generation`. Returns `None` without removing anything if the slot has since been
+	/// freed and reused by a different listener, i.e. if `(key, generation)` is a stale handle.
+	pub fn remove_handle(&mut self, key: usize, generation: u64) -> Option<T> {
+		match self.slots.get(key) {
+			Some(Slot::Occupied(_, slot_generation)) if *slot_generation == generation => self.remove(key),
+			_ => None,
+		}
+	}
+
+	/// Returns a mutable reference to the value at `key`, if any is currently stored there.
+	pub fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+		match self.slots.get_mut(key) {
+			Some(Slot::Occupied(value, _)) => Some(value),
+			_ => None,
+		}
+	}
+
+	/// The number of keys that have ever been handed out and not yet past the end of the slab.
+	/// Used by [`dispatch`] to walk every key, including vacant ones left behind by removals.
+	///
+	/// [`dispatch`]: crate::dispatcher
+	pub fn key_bound(&self) -> usize {
+		self.slots.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.slots.iter().all(|slot| matches!(slot, Slot::Vacant(..)))
+	}
+}
@@ -1,7 +1,7 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use super::{dispatcher, DispatcherCommand, Listener};
+use super::{dispatcher, DispatcherCommand, EventSink, Listener};
 
 dispatcher!(Dispatcher<(), u32>);
 
@@ -110,6 +110,57 @@ fn stop_propagation() {
 	assert_eq!(uses, 2);
 }
 
+/// Tests that a listener can be unregistered from outside by the handle `add` returned.
+#[test]
+fn remove_by_handle() {
+	let mut dispatcher = Dispatcher::default();
+	let listener = EventListener { ret: None };
+	let handle = dispatcher.add(Box::new(listener));
+	let mut uses = 0;
+	dispatcher.dispatch(&(), &mut uses);
+	assert_eq!(uses, 1);
+
+	assert!(dispatcher.remove(handle));
+	dispatcher.dispatch(&(), &mut uses);
+	assert_eq!(uses, 1);
+
+	assert!(!dispatcher.remove(handle));
+}
+
+/// Tests that removing a listener does not shift the handles of listeners registered after it, unlike
+/// `Vec::swap_remove` would.
+#[test]
+fn handles_stable_across_removal() {
+	let mut dispatcher = Dispatcher::default();
+	let first = dispatcher.add(Box::new(EventListener { ret: Some(DispatcherCommand::StopListening) }));
+	let second = dispatcher.add(Box::new(EventListener { ret: None }));
+
+	let mut uses = 0;
+	dispatcher.dispatch(&(), &mut uses);
+	assert_eq!(uses, 2);
+	assert!(!dispatcher.remove(first));
+	assert!(dispatcher.remove(second));
+}
+
+/// Tests that a handle whose slot was freed and then reused by an unrelated listener (an ABA hazard
+/// for a plain key-reusing slab) is rejected by `remove`, rather than unregistering the new listener
+/// that happens to now occupy the same slot.
+#[test]
+fn handle_rejected_after_slot_reused_by_unrelated_listener() {
+	let mut dispatcher = Dispatcher::default();
+	let first = dispatcher.add(Box::new(EventListener { ret: None }));
+	assert!(dispatcher.remove(first));
+
+	// Reuses the slot `first` pointed at.
+	let second = dispatcher.add(Box::new(EventListener { ret: None }));
+
+	assert!(!dispatcher.remove(first));
+	let mut uses = 0;
+	dispatcher.dispatch(&(), &mut uses);
+	assert_eq!(uses, 1);
+	assert!(dispatcher.remove(second));
+}
+
 /// Tests that both the listener is removed and propagation is stopped if both are requested.
 #[test]
 fn stop_listening_and_propagation() {
@@ -173,3 +224,172 @@ fn lifetimes() {
 	dispatcher.dispatch(&event, &mut event_mut);
 	assert_eq!(*event_mut.b, true);
 }
+
+/// Tests that a `QueueingListener` can raise a follow-up event, and that all listeners see the
+/// current event before any queued event is processed (breadth-first order).
+#[test]
+fn queued_dispatch_is_breadth_first() {
+	dispatcher!(QueueDispatcher<u32, Vec<u32>>);
+
+	let mut dispatcher = QueueDispatcher::default();
+	dispatcher.add_queueing(Box::new(move |event: &u32, seen: &mut Vec<u32>, sink: &mut EventSink<u32>| {
+		seen.push(*event);
+		if *event < 2 {
+			sink.push(*event + 1);
+			sink.push(*event + 10);
+		}
+		None
+	}));
+
+	let mut seen = Vec::new();
+	dispatcher.dispatch_queued(0, &mut seen);
+	assert_eq!(seen, vec![0, 1, 10, 2, 11]);
+}
+
+/// Tests that `set_max_queue_depth` bounds how many queued events are dispatched.
+#[test]
+fn queued_dispatch_respects_max_depth() {
+	dispatcher!(QueueDispatcher<u32, u32>);
+
+	let mut dispatcher = QueueDispatcher::default();
+	dispatcher.set_max_queue_depth(3);
+	dispatcher.add_queueing(Box::new(move |event: &u32, waves: &mut u32, sink: &mut EventSink<u32>| {
+		*waves += 1;
+		sink.push(*event + 1);
+		None
+	}));
+
+	let mut waves = 0;
+	dispatcher.dispatch_queued(0, &mut waves);
+	assert_eq!(waves, 3);
+}
+
+/// Tests that a `; Sync` dispatcher can be sent to another thread and accepts a `Weak<Mutex<L>>` listener.
+#[test]
+fn sync_dispatcher_allows_mutex_listener() {
+	use std::sync::{Arc, Mutex};
+
+	dispatcher!(SyncDispatcher<(), u32>; Sync);
+
+	struct EventListener {
+		uses: u32,
+	}
+
+	impl Listener<(), u32> for EventListener {
+		fn on_event(&mut self, _: &(), event_mut: &mut u32) -> Option<DispatcherCommand> {
+			self.uses += 1;
+			*event_mut += 1;
+			None
+		}
+	}
+
+	let mut dispatcher = SyncDispatcher::default();
+	let listener = Arc::new(Mutex::new(EventListener { uses: 0 }));
+	dispatcher.add(Box::new(Arc::downgrade(&listener)));
+
+	let uses = std::thread::spawn(move || {
+		let mut uses = 0;
+		dispatcher.dispatch(&(), &mut uses);
+		uses
+	}).join().unwrap();
+
+	assert_eq!(uses, 1);
+	assert_eq!(listener.lock().unwrap().uses, 1);
+}
+
+/// Tests that `dispatch_default` only reaches the dispatcher installed by `with_default`, and only
+/// for the duration of the closure passed to it.
+#[test]
+fn ambient_with_default_scopes_to_closure() {
+	dispatcher!(AmbientDispatcher<(), u32>);
+
+	let mut dispatcher = AmbientDispatcher::default();
+	dispatcher.add(Box::new(|_: &(), event_mut: &mut u32| {
+		*event_mut += 1;
+		None
+	}));
+
+	let mut uses = 0;
+	AmbientDispatcher::dispatch_default(&(), &mut uses);
+	assert_eq!(uses, 0);
+
+	dispatcher.with_default(|| {
+		AmbientDispatcher::dispatch_default(&(), &mut uses);
+	});
+	assert_eq!(uses, 1);
+
+	AmbientDispatcher::dispatch_default(&(), &mut uses);
+	assert_eq!(uses, 1);
+}
+
+/// Tests that `dispatch_default` falls back to the `set_global_default` dispatcher when no
+/// thread-local default has been installed via `with_default`.
+#[test]
+fn ambient_global_default_used_without_with_default() {
+	dispatcher!(GlobalDispatcher<(), u32>; Sync);
+
+	let dispatcher: &'static mut GlobalDispatcher = Box::leak(Box::new(GlobalDispatcher::default()));
+	dispatcher.add(Box::new(|_: &(), event_mut: &mut u32| {
+		*event_mut += 1;
+		None
+	}));
+	GlobalDispatcher::set_global_default(dispatcher);
+
+	let mut uses = 0;
+	GlobalDispatcher::dispatch_default(&(), &mut uses);
+	assert_eq!(uses, 1);
+}
+
+/// Tests that many threads dispatching to the same global default concurrently each get a consistent
+/// count back, with no lost or duplicated updates from aliasing access to the dispatcher.
+#[test]
+fn ambient_global_default_serializes_concurrent_dispatch() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	dispatcher!(ConcurrentGlobalDispatcher<(), usize>; Sync);
+
+	let dispatcher: &'static mut ConcurrentGlobalDispatcher = Box::leak(Box::new(ConcurrentGlobalDispatcher::default()));
+	dispatcher.add(Box::new(|_: &(), event_mut: &mut usize| {
+		*event_mut += 1;
+		None
+	}));
+	ConcurrentGlobalDispatcher::set_global_default(dispatcher);
+
+	let total_dispatched = AtomicUsize::new(0);
+	std::thread::scope(|scope| {
+		for _ in 0..8 {
+			scope.spawn(|| {
+				for _ in 0..100 {
+					let mut uses = 0;
+					ConcurrentGlobalDispatcher::dispatch_default(&(), &mut uses);
+					total_dispatched.fetch_add(uses, Ordering::Relaxed);
+				}
+			});
+		}
+	});
+
+	assert_eq!(total_dispatched.load(Ordering::Relaxed), 800);
+}
+
+/// Tests that a listener which re-enters `dispatch_default` for the same dispatcher type (e.g. because
+/// handling one event raises another) does not receive a second, aliasing `&mut` to the dispatcher -
+/// the re-entrant call is treated as if no dispatcher were installed.
+#[test]
+fn ambient_dispatch_default_rejects_reentrant_call() {
+	dispatcher!(ReentrantDispatcher<u32, u32>);
+
+	let mut dispatcher = ReentrantDispatcher::default();
+	dispatcher.add(Box::new(|event: &u32, event_mut: &mut u32| {
+		if *event == 0 {
+			ReentrantDispatcher::dispatch_default(&1, event_mut);
+		}
+		*event_mut += 1;
+		None
+	}));
+
+	let mut uses = 0;
+	dispatcher.with_default(|| {
+		ReentrantDispatcher::dispatch_default(&0, &mut uses);
+	});
+	assert_eq!(uses, 1);
+}